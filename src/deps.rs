@@ -0,0 +1,305 @@
+//! Breadth-first resolution of a crate version's transitive dependency closure.
+
+use std::collections::{HashSet, VecDeque};
+
+/// The set of feature names active on `version` given `all_features`/`no_default_features`,
+/// resolved by walking the `[features]` table starting from `default` (unless suppressed).
+///
+/// This is what actually determines which *optional* dependencies are pulled in: an entry in a
+/// feature's list either names another feature (expanded in turn) or names a dependency (via
+/// the bare `dep_name`, `dep_name/feature`, `dep_name?/feature`, or `dep:dep_name` syntax), which
+/// activates that dependency. `Dependency::has_default_features()` is unrelated to any of this —
+/// it's whether *that dependency's own* default features are requested, not whether the
+/// depending crate's default feature is what pulled it in.
+///
+/// With `all_features`, every declared feature and every optional dependency is active.
+fn active_features(version: &crates_index::Version, all_features: bool, no_default_features: bool) -> HashSet<String> {
+    let features = version.features();
+    if all_features {
+        let mut active: HashSet<String> = features.keys().cloned().collect();
+        active.extend(version.dependencies().iter().filter(|dep| dep.is_optional()).map(|dep| dep.name().to_owned()));
+        return active;
+    }
+
+    let mut active = HashSet::new();
+    let mut queue = VecDeque::new();
+    if !no_default_features {
+        queue.push_back("default".to_owned());
+    }
+
+    while let Some(name) = queue.pop_front() {
+        if !active.insert(name.clone()) {
+            continue;
+        }
+        for entry in features.get(&name).into_iter().flatten() {
+            let entry = entry.strip_prefix("dep:").unwrap_or(entry);
+            let name = entry.split('/').next().unwrap_or(entry).trim_end_matches('?');
+            if features.contains_key(name) {
+                queue.push_back(name.to_owned());
+            } else {
+                active.insert(name.to_owned());
+            }
+        }
+    }
+    active
+}
+
+/// Walk the dependency graph of `root` breadth-first, resolving each non-dev dependency to the
+/// newest non-prerelease version of it in `index` that matches the declared requirement, and
+/// deduplicating by `(name, exact_version)` so diamond dependencies are only visited once.
+///
+/// Required dependencies are always followed; optional ones are only followed when
+/// [`active_features`] says they're actually enabled, which is what `--all-features` and
+/// `--no-default-features` affect.
+pub fn closure(
+    index: &crates_index::Index,
+    root: &crates_index::Version,
+    all_features: bool,
+    no_default_features: bool,
+) -> Vec<crates_index::Version> {
+    closure_with(root, all_features, no_default_features, |name| {
+        index.crate_(name).map(|krate| krate.versions().to_vec())
+    })
+}
+
+/// The guts of [`closure`], taking a `lookup` of a crate name to its available versions instead
+/// of a whole `crates_index::Index`, so the resolution/dedup logic can be exercised without a
+/// real (or even fake-on-disk) index.
+fn closure_with(
+    root: &crates_index::Version,
+    all_features: bool,
+    no_default_features: bool,
+    mut lookup: impl FnMut(&str) -> Option<Vec<crates_index::Version>>,
+) -> Vec<crates_index::Version> {
+    let mut seen: HashSet<(String, String)> = HashSet::new();
+    seen.insert((root.name().to_owned(), root.version().to_owned()));
+
+    let mut queue = VecDeque::new();
+    queue.push_back(root.clone());
+
+    let mut resolved = Vec::new();
+    while let Some(version) = queue.pop_front() {
+        let active_features = active_features(&version, all_features, no_default_features);
+        for dep in version.dependencies() {
+            if dep.kind() == crates_index::DependencyKind::Dev {
+                continue;
+            }
+            if dep.is_optional() && !active_features.contains(dep.name()) {
+                continue;
+            }
+
+            let versions = match lookup(dep.crate_name()) {
+                Some(versions) => versions,
+                None => {
+                    tracing::warn!("dependency {} not found in the index, skipping", dep.crate_name());
+                    continue;
+                }
+            };
+
+            let req = match dep.requirement().parse::<semver::VersionReq>() {
+                Ok(req) => req,
+                Err(err) => {
+                    tracing::warn!("could not parse requirement {:?} for {}: {err:#?}", dep.requirement(), dep.crate_name());
+                    continue;
+                }
+            };
+
+            let chosen = versions
+                .iter()
+                .filter(|v| !v.is_yanked())
+                .filter_map(|v| semver::Version::parse(v.version()).ok().map(|num| (num, v)))
+                .filter(|(num, _)| num.pre.is_empty() && req.matches(num))
+                .max_by(|(a, _), (b, _)| a.cmp(b));
+
+            let Some((_, chosen)) = chosen else {
+                tracing::warn!("no version of {} matches {}, skipping", dep.crate_name(), dep.requirement());
+                continue;
+            };
+
+            if seen.insert((chosen.name().to_owned(), chosen.version().to_owned())) {
+                queue.push_back(chosen.clone());
+                resolved.push(chosen.clone());
+            }
+        }
+    }
+    resolved
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Build a `crates_index::Version` from the same JSON shape as a crates.io index file line,
+    /// since that's what the type is meant to be deserialized from.
+    fn version(json: serde_json::Value) -> crates_index::Version {
+        serde_json::from_value(json).expect("valid version JSON")
+    }
+
+    fn dep(name: &str, req: &str) -> serde_json::Value {
+        serde_json::json!({
+            "name": name,
+            "req": req,
+            "features": [],
+            "optional": false,
+            "default_features": true,
+            "target": null,
+            "kind": "normal",
+        })
+    }
+
+    fn optional_dep(name: &str, req: &str) -> serde_json::Value {
+        let mut dep = dep(name, req);
+        dep["optional"] = serde_json::json!(true);
+        dep
+    }
+
+    fn base_version(name: &str, vers: &str) -> serde_json::Value {
+        serde_json::json!({
+            "name": name,
+            "vers": vers,
+            "deps": [],
+            "cksum": "0".repeat(64),
+            "features": {},
+            "yanked": false,
+            "links": null,
+        })
+    }
+
+    #[test]
+    fn active_features_defaults_to_the_default_feature() {
+        let mut v = base_version("root", "1.0.0");
+        v["features"] = serde_json::json!({"default": ["foo"]});
+        let active = active_features(&version(v), false, false);
+        assert_eq!(active, HashSet::from(["default".to_owned(), "foo".to_owned()]));
+    }
+
+    #[test]
+    fn active_features_no_default_features_suppresses_default() {
+        let mut v = base_version("root", "1.0.0");
+        v["features"] = serde_json::json!({"default": ["foo"]});
+        let active = active_features(&version(v), false, true);
+        assert!(active.is_empty());
+    }
+
+    #[test]
+    fn active_features_all_features_includes_every_declared_feature() {
+        let mut v = base_version("root", "1.0.0");
+        v["features"] = serde_json::json!({"default": ["foo"], "extra": ["bar"]});
+        let active = active_features(&version(v), true, false);
+        assert_eq!(active, HashSet::from(["default".to_owned(), "extra".to_owned()]));
+    }
+
+    #[test]
+    fn active_features_expands_transitively_referenced_features() {
+        let mut v = base_version("root", "1.0.0");
+        v["features"] = serde_json::json!({"default": ["extra"], "extra": ["foo"]});
+        let active = active_features(&version(v), false, false);
+        assert!(active.contains("foo"));
+    }
+
+    #[test]
+    fn active_features_handles_explicit_dep_colon_syntax() {
+        let mut v = base_version("root", "1.0.0");
+        v["features"] = serde_json::json!({"default": ["dep:foo"]});
+        let active = active_features(&version(v), false, false);
+        assert!(active.contains("foo"));
+    }
+
+    #[test]
+    fn active_features_handles_weak_dep_slash_feature_syntax() {
+        let mut v = base_version("root", "1.0.0");
+        v["features"] = serde_json::json!({"default": ["foo?/bar"]});
+        let active = active_features(&version(v), false, false);
+        assert!(active.contains("foo"));
+    }
+
+    #[test]
+    fn closure_follows_required_deps_regardless_of_features() {
+        let mut root = base_version("root", "1.0.0");
+        root["deps"] = serde_json::json!([dep("a", "^1")]);
+        let a = version(base_version("a", "1.0.0"));
+
+        let resolved = closure_with(&version(root), false, true, |name| match name {
+            "a" => Some(vec![a.clone()]),
+            _ => None,
+        });
+
+        assert_eq!(Vec::from_iter(resolved.iter().map(|v| v.name())), vec!["a"]);
+    }
+
+    #[test]
+    fn closure_skips_optional_deps_not_enabled_by_default_feature() {
+        let mut root = base_version("root", "1.0.0");
+        root["deps"] = serde_json::json!([optional_dep("a", "^1")]);
+        let a = version(base_version("a", "1.0.0"));
+
+        let resolved = closure_with(&version(root), false, false, |_| Some(vec![a.clone()]));
+
+        assert!(resolved.is_empty());
+    }
+
+    #[test]
+    fn closure_follows_optional_deps_enabled_by_default_feature() {
+        let mut root = base_version("root", "1.0.0");
+        root["deps"] = serde_json::json!([optional_dep("a", "^1")]);
+        root["features"] = serde_json::json!({"default": ["a"]});
+        let a = version(base_version("a", "1.0.0"));
+
+        let resolved = closure_with(&version(root), false, false, |_| Some(vec![a.clone()]));
+
+        assert_eq!(Vec::from_iter(resolved.iter().map(|v| v.name())), vec!["a"]);
+    }
+
+    #[test]
+    fn closure_skips_dev_dependencies() {
+        let mut root = base_version("root", "1.0.0");
+        let mut dev_dep = dep("a", "^1");
+        dev_dep["kind"] = serde_json::json!("dev");
+        root["deps"] = serde_json::json!([dev_dep]);
+        let a = version(base_version("a", "1.0.0"));
+
+        let resolved = closure_with(&version(root), false, true, |_| Some(vec![a.clone()]));
+
+        assert!(resolved.is_empty());
+    }
+
+    #[test]
+    fn closure_dedupes_diamond_dependencies() {
+        // root depends on both "a" and "b", which both depend on "c" 1.0.0.
+        let mut root = base_version("root", "1.0.0");
+        root["deps"] = serde_json::json!([dep("a", "^1"), dep("b", "^1")]);
+
+        let mut a = base_version("a", "1.0.0");
+        a["deps"] = serde_json::json!([dep("c", "^1")]);
+        let mut b = base_version("b", "1.0.0");
+        b["deps"] = serde_json::json!([dep("c", "^1")]);
+        let c = version(base_version("c", "1.0.0"));
+
+        let resolved = closure_with(&version(root), false, true, |name| match name {
+            "a" => Some(vec![version(a.clone())]),
+            "b" => Some(vec![version(b.clone())]),
+            "c" => Some(vec![c.clone()]),
+            _ => None,
+        });
+
+        let c_count = resolved.iter().filter(|v| v.name() == "c").count();
+        assert_eq!(c_count, 1, "c should only be resolved once despite being a dependency of both a and b");
+    }
+
+    #[test]
+    fn closure_picks_the_newest_matching_non_prerelease_version() {
+        let mut root = base_version("root", "1.0.0");
+        root["deps"] = serde_json::json!([dep("a", "^1")]);
+
+        let candidates = vec![
+            version(base_version("a", "1.0.0")),
+            version(base_version("a", "1.2.0")),
+            version(base_version("a", "2.0.0")), // doesn't match ^1
+            version(base_version("a", "1.3.0-pre")), // prerelease, skipped
+        ];
+
+        let resolved = closure_with(&version(root), false, true, |_| Some(candidates.clone()));
+
+        assert_eq!(Vec::from_iter(resolved.iter().map(|v| v.version())), vec!["1.2.0"]);
+    }
+}
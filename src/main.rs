@@ -1,6 +1,8 @@
 mod crate_name;
 mod package_id_spec;
 mod cache;
+mod deps;
+mod pool;
 mod unpack;
 
 use std::io::Read;
@@ -27,6 +29,15 @@ enum Command {
     Dl(App),
 }
 
+/// Output format for `--list`.
+#[derive(Debug, Clone, Copy, clap::ArgEnum)]
+enum ListFormat {
+    /// Human-readable, aligned table.
+    Text,
+    /// A JSON array of objects, one per version, for scripting.
+    Json,
+}
+
 #[derive(Debug, Parser)]
 struct App {
     /// Specify this flag to have the crate extracted automatically.
@@ -62,6 +73,62 @@ struct App {
     /// Slow down operations for manually testing UI
     #[clap(long, hide = true)]
     slooooow: bool,
+
+    /// Maximum number of crates to download and extract concurrently.
+    #[clap(short, long, default_value_t = 16)]
+    jobs: usize,
+
+    /// Also download every transitive dependency of the selected version(s), so the result is a
+    /// full offline build input set rather than just the requested crate(s).
+    #[clap(long)]
+    deps: bool,
+
+    /// When used with --deps, also follow optional dependencies (as if all features were
+    /// enabled).
+    #[clap(long)]
+    all_features: bool,
+
+    /// When used with --deps, don't follow dependencies that are only pulled in by default
+    /// features.
+    #[clap(long)]
+    no_default_features: bool,
+
+    /// Download every version matching the constraint instead of just the newest one, each to
+    /// its own `name-version.crate` file. Conflicts with --output.
+    #[clap(long)]
+    all_versions: bool,
+
+    /// Use an already-cloned crates.io-index at PATH instead of the cargo-managed copy.
+    ///
+    /// Useful for air-gapped mirroring or CI where the index is maintained separately.
+    #[clap(long, value_name = "PATH")]
+    index: Option<std::path::PathBuf>,
+
+    /// Don't update the index over the network, only read what's already on disk (or in the
+    /// cargo cache). Most useful together with --index.
+    #[clap(long)]
+    offline: bool,
+
+    /// Instead of silently picking the newest matching version, show all matching versions in a
+    /// terminal menu and let you choose exactly which one(s) to download.
+    ///
+    /// Falls back to the default behaviour when stdin is not a terminal.
+    #[clap(long)]
+    interactive: bool,
+
+    /// Print all available versions of the crate(s) (with yank status and which one the current
+    /// constraint would select) instead of downloading anything.
+    #[clap(long)]
+    list: bool,
+
+    /// Output format used by --list.
+    #[clap(long, arg_enum, default_value = "text")]
+    format: ListFormat,
+
+    /// Number of times to retry a download after a transient error or a dropped connection,
+    /// resuming from where it left off via a `Range` request, before giving up.
+    #[clap(long, default_value_t = 5)]
+    retries: u32,
 }
 
 /// Failed to acquire one or more crates, see above for details
@@ -75,33 +142,58 @@ impl App {
         }
     }
 
+    /// Open either the cargo-managed crates.io index, or the local clone given via `--index`.
+    #[fehler::throws]
+    fn open_index(&self) -> crates_index::Index {
+        match &self.index {
+            Some(path) => crates_index::Index::with_path(path, crates_index::INDEX_GIT_URL)?,
+            None => crates_index::Index::new_cargo_default()?,
+        }
+    }
+
     #[fehler::throws]
     #[tracing::instrument(fields(%self))]
     fn run(&'static self) {
         if self.specs.len() > 1 && self.output.is_some() {
             fehler::throw!(anyhow!("cannot use --output with multiple crates"));
         }
+        if self.deps && self.output.is_some() {
+            fehler::throw!(anyhow!("cannot use --output with --deps"));
+        }
+        if self.all_versions && self.output.is_some() {
+            fehler::throw!(anyhow!("cannot use --output with --all-versions"));
+        }
+        if self.interactive && self.output.is_some() {
+            fehler::throw!(anyhow!("cannot use --output with --interactive"));
+        }
+        if self.list && self.output.is_some() {
+            fehler::throw!(anyhow!("cannot use --output with --list"));
+        }
 
         let bars: &indicatif::MultiProgress = Box::leak(Box::new(indicatif::MultiProgress::new()));
         let spawning: &std::sync::atomic::AtomicBool = Box::leak(Box::new(std::sync::atomic::AtomicBool::new(true)));
         let thread = std::thread::spawn(move || {
-            let mut index = crates_index::Index::new_cargo_default()?;
+            let mut index = self.open_index()?;
             let bar = bars.add(indicatif::ProgressBar::new_spinner()).with_style(indicatif::ProgressStyle::default_spinner().template(SPINNER_TEMPLATE))
                 .with_prefix("crates.io index")
-            .with_message("updating");
+            .with_message(if self.offline { "offline, using index as-is" } else { "updating" });
             bar.enable_steady_tick(100);
-            index.update()?;
+            if !self.offline {
+                index.update()?;
+            }
             self.slow();
 
             bar.set_style(indicatif::ProgressStyle::default_spinner().template(SUCCESS_SPINNER_TEMPLATE));
-            bar.finish_with_message("updated");
+            bar.finish_with_message(if self.offline { "offline, using index as-is" } else { "updated" });
 
+            let pool = pool::Pool::new(self.jobs);
             let threads = Vec::from_iter(self.specs.iter().map(|spec| {
                 let bar = bars.add(indicatif::ProgressBar::new_spinner()).with_style(indicatif::ProgressStyle::default_spinner().template(SPINNER_TEMPLATE));
+                let pool = pool.clone();
                 (spec, std::thread::spawn(move || {
                     bar.tick();
                     bar.set_prefix(spec.to_string());
-                    let index = crates_index::Index::new_cargo_default()?;
+                    let index = self.open_index()?;
                     bar.set_message("selecting version");
                     bar.enable_steady_tick(100);
                     self.slow();
@@ -147,127 +239,125 @@ impl App {
                         Vec::from_iter(versions.iter().map(|(num, _)| num.to_string()))
                     );
 
-                    let (_, version) = match versions.first() {
-                        Some(val) => val,
-                        None => {
-                            let yanked_versions = {
-                                let mut versions: Vec<_> = krate
-                                    .versions()
-                                    .iter()
-                                    .filter(|version| version.is_yanked())
-                                    .filter_map(|version| match semver::Version::parse(version.version()) {
-                                        Ok(num) => Some((num, version)),
-                                        Err(err) => {
-                                            tracing::warn!(
-                                                "Ignoring non-semver version {} {err:#?}",
-                                                version.version()
-                                            );
-                                            None
-                                        }
+                    if self.list {
+                        let mut all_versions: Vec<_> = krate
+                            .versions()
+                            .iter()
+                            .filter_map(|v| semver::Version::parse(v.version()).ok().map(|num| (num, v)))
+                            .collect();
+                        all_versions.sort_by(|(a, _), (b, _)| a.cmp(b).reverse());
+                        let selected = versions.first().map(|(_, v)| v.version().to_owned());
+
+                        match self.format {
+                            ListFormat::Text => {
+                                bars.suspend(|| {
+                                    println!("{:<15} {:<8} selected", "version", "yanked");
+                                    for (num, v) in &all_versions {
+                                        println!(
+                                            "{:<15} {:<8} {}",
+                                            num.to_string(),
+                                            v.is_yanked(),
+                                            if Some(v.version()) == selected.as_deref() { "*" } else { "" },
+                                        );
+                                    }
+                                });
+                            }
+                            ListFormat::Json => {
+                                let rows = Vec::from_iter(all_versions.iter().map(|(_, v)| {
+                                    serde_json::json!({
+                                        "name": v.name(),
+                                        "version": v.version(),
+                                        "yanked": v.is_yanked(),
+                                        "selected": Some(v.version()) == selected.as_deref(),
                                     })
-                                    .filter(|(num, _)| version_request.matches(num))
-                                    .collect();
-                                versions.sort_by(|(a, _), (b, _)| a.cmp(b).reverse());
-                                versions
-                            };
-                            let mut msg = "no matching version found".to_owned();
-                            if let Some((_, version)) = yanked_versions.first() {
-                                use std::fmt::Write;
-                                write!(msg, "; the yanked version {} {} matched, use `--allow-yanked` to download it", version.name(), version.version())?;
+                                }));
+                                let json = serde_json::to_string(&rows)?;
+                                bars.suspend(|| println!("{json}"));
                             }
-                            bar.set_style(indicatif::ProgressStyle::default_spinner().template(FAILURE_SPINNER_TEMPLATE));
-                            bar.finish_with_message(msg);
-                            return Err(LoggedError.into());
                         }
-                    };
 
-                    let version_str = stylish::format!("{:(fg=magenta)} {:(fg=magenta)}", version.name(), version.version());
+                        bar.set_style(indicatif::ProgressStyle::default_spinner().template(SUCCESS_SPINNER_TEMPLATE));
+                        bar.finish_with_message(format!("listed {} version(s)", all_versions.len()));
+                        return Result::<(), anyhow::Error>::Ok(());
+                    }
 
-                    let output = self.output.clone().unwrap_or_else(|| if self.extract {
-                        format!("{}-{}", version.name(), version.version())
-                    } else {
-                        format!("{}-{}.crate", version.name(), version.version())
-                    });
+                    if versions.is_empty() {
+                        let yanked_versions = {
+                            let mut versions: Vec<_> = krate
+                                .versions()
+                                .iter()
+                                .filter(|version| version.is_yanked())
+                                .filter_map(|version| match semver::Version::parse(version.version()) {
+                                    Ok(num) => Some((num, version)),
+                                    Err(err) => {
+                                        tracing::warn!(
+                                            "Ignoring non-semver version {} {err:#?}",
+                                            version.version()
+                                        );
+                                        None
+                                    }
+                                })
+                                .filter(|(num, _)| version_request.matches(num))
+                                .collect();
+                            versions.sort_by(|(a, _), (b, _)| a.cmp(b).reverse());
+                            versions
+                        };
+                        let mut msg = "no matching version found".to_owned();
+                        if let Some((_, version)) = yanked_versions.first() {
+                            use std::fmt::Write;
+                            write!(msg, "; the yanked version {} {} matched, use `--allow-yanked` to download it", version.name(), version.version())?;
+                        }
+                        bar.set_style(indicatif::ProgressStyle::default_spinner().template(FAILURE_SPINNER_TEMPLATE));
+                        bar.finish_with_message(msg);
+                        return Err(LoggedError.into());
+                    }
 
-                    let cached = if self.cache {
-                        bar.set_message(stylish::ansi::format!("checking cache for {:s}", version_str));
-                        self.slow();
-                        cache::lookup(&index, version)
-                    } else {
-                        Err(anyhow!("cache disabled by flag"))
-                    };
+                    if self.all_versions {
+                        let count = versions.len();
+                        self.fetch_each(bars, &pool, Vec::from_iter(versions.iter().map(|(_, version)| (*version).clone())))?;
+                        bar.set_style(indicatif::ProgressStyle::default_spinner().template(SUCCESS_SPINNER_TEMPLATE));
+                        bar.finish_with_message(format!("fetched {} version(s)", count));
+                        return Result::<(), anyhow::Error>::Ok(());
+                    }
 
-                    match cached {
-                        Ok(path) => {
-                            tracing::debug!("found cached crate for {} {} at {}", version.name(), version.version(), path.display());
-                            if self.extract {
-                                bar.set_message(stylish::ansi::format!("extracting {:s} to {:(fg=blue)}", version_str, output));
-                                let file = std::fs::File::open(path)?;
-                                bar.reset();
-                                bar.set_length(file.metadata()?.len());
-                                bar.set_style(indicatif::ProgressStyle::default_bar().template(DOWNLOAD_TEMPLATE));
-                                let archive = tar::Archive::new(flate2::bufread::GzDecoder::new(bar.wrap_read(std::io::BufReader::new(file))));
-                                unpack::unpack(version, archive, &output)?;
-                                self.slow();
-                                bar.set_style(indicatif::ProgressStyle::default_spinner().template(SUCCESS_SPINNER_TEMPLATE));
-                                bar.finish_with_message(stylish::ansi::format!("extracted {:s} to {:(fg=blue)}", version_str, output));
-                            } else {
-                                bar.set_message(stylish::ansi::format!("writing {:s} to {:(fg=blue)}", version_str, output));
-                                self.slow();
-                                std::fs::copy(path, &output)?;
-                                bar.set_style(indicatif::ProgressStyle::default_spinner().template(SUCCESS_SPINNER_TEMPLATE));
-                                bar.finish_with_message(stylish::ansi::format!("written {:s} to {:(fg=blue)}", version_str, output));
-                            }
-                        }
-                        Err(err) => {
-                            use sha2::Digest;
-                            tracing::debug!("{err:?}");
-                            let url = version.download_url(&index.index_config()?).context("missing download url")?;
-                            bar.set_message(stylish::ansi::format!("downloading {:s}", version_str));
-                            let resp = ureq::get(&url).set("User-Agent", USER_AGENT).call()?;
-                            let mut data;
-                            if let Some(len) = resp.header("Content-Length").and_then(|s| s.parse::<usize>().ok()) {
-                                data = Vec::with_capacity(len);
-                                bar.reset();
-                                bar.set_length(u64::try_from(len)?);
-                                bar.set_style(indicatif::ProgressStyle::default_bar().template(DOWNLOAD_TEMPLATE));
-                            } else {
-                                data = Vec::with_capacity(usize::try_from(CRATE_SIZE_LIMIT)?);
-                            }
-                            bar.wrap_read(resp.into_reader()).take(CRATE_SIZE_LIMIT).read_to_end(&mut data)?;
-                            self.slow();
-                            tracing::debug!("downloaded {} {} ({} bytes)", version.name(), version.version(), data.len());
-                            bar.set_style(indicatif::ProgressStyle::default_spinner().template(SPINNER_TEMPLATE));
-                            bar.set_message(stylish::ansi::format!("verifying checksum of {:s}", version_str));
-                            let calculated_checksum = sha2::Sha256::digest(&data);
-                            if calculated_checksum.as_slice() != version.checksum() {
-                                tracing::debug!("invalid checksum, expected {} but got {}", hex::encode(version.checksum()), hex::encode(calculated_checksum));
+                    if self.interactive {
+                        if console::Term::stdin().features().is_attended() {
+                            let default = versions.iter().position(|(_, version)| !version.is_yanked()).unwrap_or(0);
+                            let items = Vec::from_iter(versions.iter().map(|(num, version)| {
+                                format!("{num}{}", if version.is_yanked() { " (yanked)" } else { "" })
+                            }));
+                            let chosen = bars.suspend(|| {
+                                dialoguer::MultiSelect::new()
+                                    .with_prompt(format!("select version(s) of {} to download", spec.name))
+                                    .items(&items)
+                                    .item_checked(default, true)
+                                    .interact()
+                            })?;
+                            if chosen.is_empty() {
                                 bar.set_style(indicatif::ProgressStyle::default_spinner().template(FAILURE_SPINNER_TEMPLATE));
-                                bar.finish_with_message("invalid checksum");
+                                bar.finish_with_message("no version(s) selected");
                                 return Err(LoggedError.into());
                             }
-                            tracing::debug!("verified checksum ({})", hex::encode(version.checksum()));
-                            self.slow();
-
-                            if self.extract {
-                                bar.set_message(stylish::ansi::format!("extracting {:s} to {:(fg=blue)}", version_str, output));
-                                bar.reset();
-                                bar.set_length(u64::try_from(data.len())?);
-                                bar.set_style(indicatif::ProgressStyle::default_bar().template(DOWNLOAD_TEMPLATE));
-                                let archive = tar::Archive::new(flate2::bufread::GzDecoder::new(bar.wrap_read(std::io::Cursor::new(data))));
-                                unpack::unpack(version, archive, &output)?;
-                                self.slow();
-                                bar.set_style(indicatif::ProgressStyle::default_spinner().template(SUCCESS_SPINNER_TEMPLATE));
-                                bar.finish_with_message(stylish::ansi::format!("extracted {:s} to {:(fg=blue)}", version_str, output));
-                            } else {
-                                bar.set_message(stylish::ansi::format!("writing {:s} to {:(fg=blue)}", version_str, output));
-                                std::fs::write(&output, data)?;
-                                self.slow();
-                                bar.set_style(indicatif::ProgressStyle::default_spinner().template(SUCCESS_SPINNER_TEMPLATE));
-                                bar.finish_with_message(stylish::ansi::format!("written {:s} to {:(fg=blue)}", version_str, output));
-                            }
+                            let count = chosen.len();
+                            self.fetch_each(bars, &pool, Vec::from_iter(chosen.into_iter().map(|i| versions[i].1.clone())))?;
+                            bar.set_style(indicatif::ProgressStyle::default_spinner().template(SUCCESS_SPINNER_TEMPLATE));
+                            bar.finish_with_message(format!("fetched {} version(s)", count));
+                            return Result::<(), anyhow::Error>::Ok(());
                         }
+                        tracing::warn!("stdin is not a terminal, ignoring --interactive");
                     }
+
+                    let (_, version) = versions.first().expect("checked non-empty above");
+
+                    let version_str = stylish::format!("{:(fg=magenta)} {:(fg=magenta)}", version.name(), version.version());
+
+                    let output = self.output.clone().unwrap_or_else(|| if self.extract {
+                        format!("{}-{}", version.name(), version.version())
+                    } else {
+                        format!("{}-{}.crate", version.name(), version.version())
+                    });
+
+                    self.fetch_with_deps(bars, &pool, &bar, &index, version, &version_str, &output)?;
                     Result::<(), anyhow::Error>::Ok(())
                 }))
             }));
@@ -302,6 +392,235 @@ impl App {
             fehler::throw!(LoggedError);
         }
     }
+
+    /// Fetch each of `versions` concurrently, each going through
+    /// [`fetch_with_deps`](Self::fetch_with_deps). Used by `--all-versions` and by the
+    /// multiselect form of `--interactive`.
+    ///
+    /// The per-version threads are spawned through their own pool (sized like `--jobs`, but
+    /// distinct from `pool`, which continues to gate the downloads and dependency threads each
+    /// one goes on to use) so at most `--jobs` of them are ever alive at once — some crates have
+    /// hundreds of published versions, and spawning one live OS thread per version up front
+    /// regardless of `--jobs` would defeat the point of having a concurrency cap at all. A single
+    /// shared pool can't do both jobs here: each spawned thread would hold its own slot for the
+    /// whole call to `fetch_with_deps`, which itself needs to acquire a slot from the same pool,
+    /// deadlocking as soon as all slots are held by outer threads.
+    #[fehler::throws]
+    fn fetch_each(&'static self, bars: &'static indicatif::MultiProgress, pool: &pool::Pool, versions: Vec<crates_index::Version>) {
+        let spawner = pool::Pool::new(self.jobs);
+        let threads = Vec::from_iter(versions.into_iter().map(|version| {
+            let bar = bars.add(indicatif::ProgressBar::new_spinner()).with_style(indicatif::ProgressStyle::default_spinner().template(SPINNER_TEMPLATE));
+            let pool = pool.clone();
+            let label = format!("{} {}", version.name(), version.version());
+            (label.clone(), spawner.spawn(move || {
+                bar.tick();
+                bar.set_prefix(label);
+                let index = self.open_index()?;
+                let version_str = stylish::format!("{:(fg=magenta)} {:(fg=magenta)}", version.name(), version.version());
+                let output = format!("{}-{}.crate", version.name(), version.version());
+                self.fetch_with_deps(bars, &pool, &bar, &index, &version, &version_str, &output)?;
+                Result::<(), anyhow::Error>::Ok(())
+            }))
+        }));
+
+        for (label, thread) in threads {
+            match thread.join() {
+                Ok(Ok(())) => (),
+                Ok(Err(e)) => {
+                    if e.is::<LoggedError>() {
+                        fehler::throw!(LoggedError);
+                    } else {
+                        fehler::throw!(e.context(format!("could not acquire {}", label)));
+                    }
+                }
+                Err(e) => std::panic::resume_unwind(e),
+            }
+        }
+    }
+
+    /// Fetch `version` as [`fetch`](Self::fetch) does, then, if `--deps` was passed, resolve its
+    /// full dependency closure and fetch each of those too, one thread per dependency.
+    ///
+    /// Threads for the dependency closure are spawned through `pool`, so at most `--jobs` of
+    /// them are ever alive at once — with a closure of hundreds of crates (common under
+    /// `--deps`), spawning one live OS thread per dependency up front regardless of `--jobs`
+    /// would defeat the point of having a concurrency cap at all.
+    #[fehler::throws]
+    fn fetch_with_deps(&'static self, bars: &'static indicatif::MultiProgress, pool: &pool::Pool, bar: &indicatif::ProgressBar, index: &crates_index::Index, version: &crates_index::Version, version_str: &str, output: &str) {
+        {
+            bar.set_message("waiting for a free download slot");
+            let _permit = pool.acquire();
+            self.fetch(bar, index, version, version_str, output)?;
+        }
+
+        if self.deps {
+            bar.set_message("resolving dependencies");
+            let resolved = deps::closure(index, version, self.all_features, self.no_default_features);
+            tracing::debug!(
+                "dependency closure: {:?}",
+                Vec::from_iter(resolved.iter().map(|v| format!("{} {}", v.name(), v.version())))
+            );
+
+            let dep_threads = Vec::from_iter(resolved.into_iter().map(|dep_version| {
+                let bar = bars.add(indicatif::ProgressBar::new_spinner()).with_style(indicatif::ProgressStyle::default_spinner().template(SPINNER_TEMPLATE));
+                let label = format!("{} {}", dep_version.name(), dep_version.version());
+                (label.clone(), pool.spawn(move || {
+                    bar.tick();
+                    bar.set_prefix(label);
+                    let index = self.open_index()?;
+                    let version_str = stylish::format!("{:(fg=magenta)} {:(fg=magenta)}", dep_version.name(), dep_version.version());
+                    let output = format!("{}-{}{}", dep_version.name(), dep_version.version(), if self.extract { "" } else { ".crate" });
+                    self.fetch(&bar, &index, &dep_version, &version_str, &output)?;
+                    Result::<(), anyhow::Error>::Ok(())
+                }))
+            }));
+
+            for (label, thread) in dep_threads {
+                match thread.join() {
+                    Ok(Ok(())) => (),
+                    Ok(Err(e)) => {
+                        if e.is::<LoggedError>() {
+                            fehler::throw!(LoggedError);
+                        } else {
+                            fehler::throw!(e.context(format!("could not acquire dependency {}", label)));
+                        }
+                    }
+                    Err(e) => std::panic::resume_unwind(e),
+                }
+            }
+        }
+    }
+
+    /// Download `url`, retrying with exponential backoff on transient errors or a short read, up
+    /// to `--retries` times. Retries resume from where the previous attempt left off via a
+    /// `Range` request, falling back to a full restart if the server doesn't honor it (i.e.
+    /// responds `200` instead of `206`).
+    #[fehler::throws]
+    fn download(&self, bar: &indicatif::ProgressBar, url: &str, version_str: &str) -> Vec<u8> {
+        let mut data = Vec::new();
+        let mut attempt = 0;
+        loop {
+            let mut request = ureq::get(url).set("User-Agent", USER_AGENT);
+            if !data.is_empty() {
+                request = request.set("Range", &format!("bytes={}-", data.len()));
+            }
+
+            let result = request.call().map_err(Error::from).and_then(|resp| {
+                let resuming = !data.is_empty() && resp.status() == 206;
+                if !data.is_empty() && !resuming {
+                    tracing::debug!("server did not honor the range request, restarting from scratch");
+                    data.clear();
+                }
+
+                if let Some(len) = resp.header("Content-Length").and_then(|s| s.parse::<usize>().ok()) {
+                    data.reserve(len);
+                    bar.reset();
+                    bar.set_position(u64::try_from(data.len())?);
+                    bar.set_length(u64::try_from(data.len() + len)?);
+                    bar.set_style(indicatif::ProgressStyle::default_bar().template(DOWNLOAD_TEMPLATE));
+                }
+
+                let remaining = CRATE_SIZE_LIMIT.saturating_sub(u64::try_from(data.len())?);
+                bar.wrap_read(resp.into_reader()).take(remaining).read_to_end(&mut data)?;
+                Result::<(), Error>::Ok(())
+            });
+
+            match result {
+                Ok(()) => break,
+                // A 4xx status other than 408/429 won't change on retry, so it is not retried.
+                Err(err) if attempt < self.retries && !matches!(
+                    err.downcast_ref::<ureq::Error>(),
+                    Some(ureq::Error::Status(code, _)) if *code < 500 && *code != 408 && *code != 429
+                ) => {
+                    attempt += 1;
+                    tracing::debug!("download attempt {attempt} failed: {err:#?}, retrying from byte {}", data.len());
+                    bar.set_message(stylish::ansi::format!("retrying download of {:s} (attempt {attempt}/{})", version_str, self.retries));
+                    std::thread::sleep(std::time::Duration::from_millis(500) * 2u32.saturating_pow((attempt - 1).min(10)));
+                }
+                Err(err) => fehler::throw!(err.context("downloading crate")),
+            }
+        }
+        data
+    }
+
+    /// Fetch `version`, either from the cargo cache or by downloading it, writing (or
+    /// extracting) it to `output`. Shared by the per-spec threads spawned from `run`.
+    ///
+    /// Does not itself bound concurrency against `--jobs`; callers are expected to hold a
+    /// `pool::Permit` (or have gone through [`Pool::spawn`](pool::Pool::spawn)) for the duration
+    /// of this call.
+    #[fehler::throws]
+    fn fetch(&self, bar: &indicatif::ProgressBar, index: &crates_index::Index, version: &crates_index::Version, version_str: &str, output: &str) {
+        let cached = if self.cache {
+            bar.set_message(stylish::ansi::format!("checking cache for {:s}", version_str));
+            self.slow();
+            cache::lookup(index, version)
+        } else {
+            Err(anyhow!("cache disabled by flag"))
+        };
+
+        match cached {
+            Ok(path) => {
+                tracing::debug!("found cached crate for {} {} at {}", version.name(), version.version(), path.display());
+                if self.extract {
+                    bar.set_message(stylish::ansi::format!("extracting {:s} to {:(fg=blue)}", version_str, output));
+                    let file = std::fs::File::open(path)?;
+                    bar.reset();
+                    bar.set_length(file.metadata()?.len());
+                    bar.set_style(indicatif::ProgressStyle::default_bar().template(DOWNLOAD_TEMPLATE));
+                    let archive = tar::Archive::new(flate2::bufread::GzDecoder::new(bar.wrap_read(std::io::BufReader::new(file))));
+                    unpack::unpack(version, archive, output)?;
+                    self.slow();
+                    bar.set_style(indicatif::ProgressStyle::default_spinner().template(SUCCESS_SPINNER_TEMPLATE));
+                    bar.finish_with_message(stylish::ansi::format!("extracted {:s} to {:(fg=blue)}", version_str, output));
+                } else {
+                    bar.set_message(stylish::ansi::format!("writing {:s} to {:(fg=blue)}", version_str, output));
+                    self.slow();
+                    std::fs::copy(path, output)?;
+                    bar.set_style(indicatif::ProgressStyle::default_spinner().template(SUCCESS_SPINNER_TEMPLATE));
+                    bar.finish_with_message(stylish::ansi::format!("written {:s} to {:(fg=blue)}", version_str, output));
+                }
+            }
+            Err(err) => {
+                use sha2::Digest;
+                tracing::debug!("{err:?}");
+                let url = version.download_url(&index.index_config()?).context("missing download url")?;
+                bar.set_message(stylish::ansi::format!("downloading {:s}", version_str));
+                let data = self.download(bar, &url, version_str)?;
+                self.slow();
+                tracing::debug!("downloaded {} {} ({} bytes)", version.name(), version.version(), data.len());
+                bar.set_style(indicatif::ProgressStyle::default_spinner().template(SPINNER_TEMPLATE));
+                bar.set_message(stylish::ansi::format!("verifying checksum of {:s}", version_str));
+                let calculated_checksum = sha2::Sha256::digest(&data);
+                if calculated_checksum.as_slice() != version.checksum() {
+                    tracing::debug!("invalid checksum, expected {} but got {}", hex::encode(version.checksum()), hex::encode(calculated_checksum));
+                    bar.set_style(indicatif::ProgressStyle::default_spinner().template(FAILURE_SPINNER_TEMPLATE));
+                    bar.finish_with_message("invalid checksum");
+                    fehler::throw!(LoggedError);
+                }
+                tracing::debug!("verified checksum ({})", hex::encode(version.checksum()));
+                self.slow();
+
+                if self.extract {
+                    bar.set_message(stylish::ansi::format!("extracting {:s} to {:(fg=blue)}", version_str, output));
+                    bar.reset();
+                    bar.set_length(u64::try_from(data.len())?);
+                    bar.set_style(indicatif::ProgressStyle::default_bar().template(DOWNLOAD_TEMPLATE));
+                    let archive = tar::Archive::new(flate2::bufread::GzDecoder::new(bar.wrap_read(std::io::Cursor::new(data))));
+                    unpack::unpack(version, archive, output)?;
+                    self.slow();
+                    bar.set_style(indicatif::ProgressStyle::default_spinner().template(SUCCESS_SPINNER_TEMPLATE));
+                    bar.finish_with_message(stylish::ansi::format!("extracted {:s} to {:(fg=blue)}", version_str, output));
+                } else {
+                    bar.set_message(stylish::ansi::format!("writing {:s} to {:(fg=blue)}", version_str, output));
+                    std::fs::write(output, data)?;
+                    self.slow();
+                    bar.set_style(indicatif::ProgressStyle::default_spinner().template(SUCCESS_SPINNER_TEMPLATE));
+                    bar.finish_with_message(stylish::ansi::format!("written {:s} to {:(fg=blue)}", version_str, output));
+                }
+            }
+        }
+    }
 }
 
 impl std::fmt::Display for App {
@@ -317,6 +636,36 @@ impl std::fmt::Display for App {
         if let Some(output) = &self.output {
             write!(f, " --output={:?}", output)?;
         }
+        if self.jobs != 16 {
+            write!(f, " --jobs={}", self.jobs)?;
+        }
+        if self.deps {
+            write!(f, " --deps")?;
+        }
+        if self.all_features {
+            write!(f, " --all-features")?;
+        }
+        if self.no_default_features {
+            write!(f, " --no-default-features")?;
+        }
+        if self.all_versions {
+            write!(f, " --all-versions")?;
+        }
+        if let Some(index) = &self.index {
+            write!(f, " --index={:?}", index)?;
+        }
+        if self.offline {
+            write!(f, " --offline")?;
+        }
+        if self.interactive {
+            write!(f, " --interactive")?;
+        }
+        if self.list {
+            write!(f, " --list")?;
+        }
+        if self.retries != 5 {
+            write!(f, " --retries={}", self.retries)?;
+        }
         write!(f, " --")?;
         for spec in &self.specs {
             write!(f, " {}", spec)?;
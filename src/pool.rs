@@ -0,0 +1,66 @@
+//! A small counting semaphore used to cap how many downloads are in flight at once.
+
+use std::sync::{Arc, Condvar, Mutex};
+
+/// Hands out [`Permit`]s up to a fixed limit, blocking callers beyond that limit until one is
+/// released.
+#[derive(Clone)]
+pub struct Pool {
+    inner: Arc<(Mutex<usize>, Condvar)>,
+    limit: usize,
+}
+
+impl Pool {
+    /// Create a pool allowing at most `limit` permits to be held at once.
+    pub fn new(limit: usize) -> Self {
+        Self {
+            inner: Arc::new((Mutex::new(0), Condvar::new())),
+            limit,
+        }
+    }
+
+    /// Block until a permit is available, then return it. Dropping the permit releases it back
+    /// to the pool.
+    pub fn acquire(&self) -> Permit {
+        let (lock, cvar) = &*self.inner;
+        let mut in_use = lock.lock().unwrap();
+        while *in_use >= self.limit {
+            in_use = cvar.wait(in_use).unwrap();
+        }
+        *in_use += 1;
+        Permit { inner: self.inner.clone() }
+    }
+
+    /// Block until a permit is available, then spawn `f` as a new OS thread holding that permit
+    /// for its entire lifetime, releasing it when `f` returns.
+    ///
+    /// Unlike calling [`acquire`](Self::acquire) from inside an already-spawned thread, this
+    /// bounds how many threads spawned this way can be *alive* at once to `limit`, rather than
+    /// just how many can be past the acquire point concurrently — important when spawning one
+    /// thread per item up front for a potentially large item count.
+    pub fn spawn<F, T>(&self, f: F) -> std::thread::JoinHandle<T>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let permit = self.acquire();
+        std::thread::spawn(move || {
+            let _permit = permit;
+            f()
+        })
+    }
+}
+
+/// A held slot in a [`Pool`], released back to the pool on drop.
+pub struct Permit {
+    inner: Arc<(Mutex<usize>, Condvar)>,
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        let (lock, cvar) = &*self.inner;
+        let mut in_use = lock.lock().unwrap();
+        *in_use -= 1;
+        cvar.notify_one();
+    }
+}